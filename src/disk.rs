@@ -0,0 +1,420 @@
+//! Disk-backed storage mode.
+//!
+//! For working sets too large to keep in RAM, [`DiskMap`] partitions keys
+//! across a power-of-two number of buckets, each a fixed-size slot region
+//! backed by a memory-mapped file rather than an in-heap `Vec`. Within a bucket
+//! keys are placed with bounded linear probing; once a key's probe window holds
+//! no free slot, the bucket's index region is rebuilt, in place if it holds
+//! tombstones to reclaim, otherwise by doubling its mapping.
+//!
+//! Because entries are written in place into the mapping, this mode constrains
+//! keys and values to `Copy`, fixed-size types.
+//!
+//! A [`DiskMap`] directory is **single-session only**: the bucket files are
+//! reset when the map is opened and no header is persisted, so reopening a
+//! directory starts from an empty map rather than recovering prior contents.
+
+use std::fs::{File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem::{self, MaybeUninit};
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+use std::collections::hash_map::DefaultHasher;
+
+use memmap2::MmapMut;
+
+/// An index slot that has never held an entry; a probe stops here.
+const EMPTY: u8 = 0;
+/// An index slot holding a live entry.
+const OCCUPIED: u8 = 1;
+/// An index slot whose entry was removed; a probe skips past it.
+const DELETED: u8 = 2;
+
+/// Configuration for [`DiskMap`] / [`HashMap::with_disk_backing`](crate::HashMap::with_disk_backing).
+pub struct DiskConfig {
+    /// Number of buckets; rounded up to a power of two.
+    pub num_buckets: usize,
+    /// Directory in which the bucket files are created.
+    pub dir: PathBuf,
+    /// Maximum slots probed within a bucket before it is grown.
+    pub max_search: usize,
+}
+
+/// Errors distinguishing which mapped region ran out of space, so callers can
+/// react to growth pressure.
+#[derive(Debug)]
+pub enum DiskError {
+    /// The bucket's index region could not be grown to fit another key.
+    IndexRegionFull,
+    /// The bucket's data region could not be grown to fit another value.
+    DataRegionFull,
+    /// An underlying filesystem or mapping operation failed.
+    Io(std::io::Error),
+}
+
+impl From<std::io::Error> for DiskError {
+    fn from(err: std::io::Error) -> Self {
+        DiskError::Io(err)
+    }
+}
+
+/// Reads a `Copy` value out of a mapped region at a byte offset.
+///
+/// # Safety
+///
+/// `offset + size_of::<T>()` must be within `buf`, and the bytes must have been
+/// written by a prior [`write_at`] of the same type.
+unsafe fn read_at<T: Copy>(buf: &[u8], offset: usize) -> T {
+    let mut value = MaybeUninit::<T>::uninit();
+    ptr::copy_nonoverlapping(
+        buf.as_ptr().add(offset),
+        value.as_mut_ptr() as *mut u8,
+        mem::size_of::<T>(),
+    );
+    value.assume_init()
+}
+
+/// Writes a `Copy` value into a mapped region at a byte offset.
+///
+/// # Safety
+///
+/// `offset + size_of::<T>()` must be within `buf`.
+unsafe fn write_at<T: Copy>(buf: &mut [u8], offset: usize, value: T) {
+    ptr::copy_nonoverlapping(
+        &value as *const T as *const u8,
+        buf.as_mut_ptr().add(offset),
+        mem::size_of::<T>(),
+    );
+}
+
+/// A single bucket: an index region of fixed-size slots and a data region of
+/// values, each mapped from its own file.
+struct Bucket<K, V> {
+    index: MmapMut,
+    data: MmapMut,
+    index_file: File,
+    data_file: File,
+    slots: usize,
+    data_cap: usize,
+    data_len: usize,
+    /// Data-region indices freed by removals, available for reuse before the
+    /// region is grown.
+    data_free: Vec<u64>,
+    slot_size: usize,
+    marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> Bucket<K, V>
+where
+    K: Copy + Hash + Eq,
+    V: Copy,
+{
+    /// Byte layout of one index slot: a state byte, the key, then the `u64`
+    /// index of its value within the data region (in units of `V`).
+    fn slot_size() -> usize {
+        1 + mem::size_of::<K>() + mem::size_of::<u64>()
+    }
+
+    fn open(dir: &Path, bucket: usize, slots: usize) -> Result<Self, DiskError> {
+        let slot_size = Self::slot_size();
+        let data_cap = slots;
+        let index_file = Self::map_file(dir, bucket, "index", slots * slot_size)?;
+        let data_file = Self::map_file(dir, bucket, "data", data_cap * mem::size_of::<V>())?;
+        let mut index = unsafe { MmapMut::map_mut(&index_file)? };
+        let data = unsafe { MmapMut::map_mut(&data_file)? };
+        // Reset any stale index bytes left by a previous session so probing
+        // never reads an OCCUPIED slot whose data offset we no longer track.
+        index.fill(EMPTY);
+        Ok(Bucket {
+            index,
+            data,
+            index_file,
+            data_file,
+            slots,
+            data_cap,
+            data_len: 0,
+            data_free: Vec::new(),
+            slot_size,
+            marker: PhantomData,
+        })
+    }
+
+    fn map_file(dir: &Path, bucket: usize, kind: &str, len: usize) -> Result<File, DiskError> {
+        let path = dir.join(format!("bucket-{bucket}.{kind}"));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        file.set_len(len as u64)?;
+        Ok(file)
+    }
+
+    fn slot_offset(&self, slot: usize) -> usize {
+        slot * self.slot_size
+    }
+
+    fn read_key(&self, slot: usize) -> K {
+        unsafe { read_at::<K>(&self.index, self.slot_offset(slot) + 1) }
+    }
+
+    fn read_data_index(&self, slot: usize) -> u64 {
+        let base = self.slot_offset(slot) + 1 + mem::size_of::<K>();
+        unsafe { read_at::<u64>(&self.index, base) }
+    }
+
+    fn state(&self, slot: usize) -> u8 {
+        self.index[self.slot_offset(slot)]
+    }
+
+    fn read_value(&self, data_index: u64) -> V {
+        unsafe { read_at::<V>(&self.data, data_index as usize * mem::size_of::<V>()) }
+    }
+
+    fn write_value(&mut self, data_index: u64, value: V) {
+        let offset = data_index as usize * mem::size_of::<V>();
+        unsafe { write_at::<V>(&mut self.data, offset, value) }
+    }
+
+    fn write_slot(&mut self, slot: usize, key: K, data_index: u64) {
+        let base = self.slot_offset(slot);
+        self.index[base] = OCCUPIED;
+        unsafe {
+            write_at::<K>(&mut self.index, base + 1, key);
+            write_at::<u64>(&mut self.index, base + 1 + mem::size_of::<K>(), data_index);
+        }
+    }
+
+    /// Probes up to `max_search` slots from `key`'s home position. Returns the
+    /// slot already holding `key`, or the first reusable slot a new entry may
+    /// take (an earlier tombstone if one was passed, otherwise the stopping
+    /// empty slot). Returns `None` if the window ends without reaching an empty
+    /// slot, even when a tombstone was passed. Tombstones are skipped so a
+    /// removal never shadows a key displaced further along its probe chain.
+    fn probe(&self, hash: u64, key: &K, max_search: usize) -> Option<Probe> {
+        let home = (hash as usize) % self.slots;
+        let mut first_deleted = None;
+        for i in 0..max_search {
+            let slot = (home + i) % self.slots;
+            match self.state(slot) {
+                EMPTY => return Some(Probe::Vacant(first_deleted.unwrap_or(slot))),
+                DELETED => {
+                    first_deleted.get_or_insert(slot);
+                }
+                _ if &self.read_key(slot) == key => return Some(Probe::Occupied(slot)),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn insert(&mut self, hash: u64, key: K, value: V, max_search: usize) -> Result<(), DiskError> {
+        match self.probe(hash, &key, max_search) {
+            Some(Probe::Occupied(slot)) => {
+                let data_index = self.read_data_index(slot);
+                self.write_value(data_index, value);
+                Ok(())
+            }
+            Some(Probe::Vacant(slot)) => {
+                let data_index = match self.data_free.pop() {
+                    Some(index) => index,
+                    None => {
+                        if self.data_len == self.data_cap {
+                            self.grow_data()?;
+                        }
+                        let index = self.data_len as u64;
+                        self.data_len += 1;
+                        index
+                    }
+                };
+                self.write_value(data_index, value);
+                self.write_slot(slot, key, data_index);
+                Ok(())
+            }
+            None => {
+                // The probe window is full. Reclaiming tombstones in place may
+                // free a usable slot; if there are none, the table must grow.
+                // A rehash leaves no tombstones, so any further retry doubles
+                // the index region until the key fits or doubling overflows.
+                let min_slots = if (0..self.slots).any(|s| self.state(s) == DELETED) {
+                    self.slots
+                } else {
+                    self.slots.checked_mul(2).ok_or(DiskError::IndexRegionFull)?
+                };
+                self.rehash(min_slots, max_search)?;
+                self.insert(hash, key, value, max_search)
+            }
+        }
+    }
+
+    fn get(&self, hash: u64, key: &K, max_search: usize) -> Option<V> {
+        match self.probe(hash, key, max_search) {
+            Some(Probe::Occupied(slot)) => Some(self.read_value(self.read_data_index(slot))),
+            _ => None,
+        }
+    }
+
+    fn remove(&mut self, hash: u64, key: &K, max_search: usize) -> Option<V> {
+        match self.probe(hash, key, max_search) {
+            Some(Probe::Occupied(slot)) => {
+                let data_index = self.read_data_index(slot);
+                let value = self.read_value(data_index);
+                let offset = self.slot_offset(slot);
+                self.index[offset] = DELETED;
+                self.data_free.push(data_index);
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Replays every live slot into a fresh index region of at least
+    /// `min_slots` slots, dropping tombstones. Grows beyond `min_slots` only
+    /// when the live entries cannot all land within `max_search` of their home.
+    /// Placement uses the same bounded probe as lookup, so no entry is ever
+    /// written beyond the window `get`/`remove` will search.
+    fn rehash(&mut self, min_slots: usize, max_search: usize) -> Result<(), DiskError> {
+        self.index.flush().map_err(|_| DiskError::IndexRegionFull)?;
+        let old: Vec<(K, u64)> = (0..self.slots)
+            .filter(|&s| self.state(s) == OCCUPIED)
+            .map(|s| (self.read_key(s), self.read_data_index(s)))
+            .collect();
+
+        let mut new_slots = min_slots;
+        let placements = loop {
+            match Self::plan_placement(&old, new_slots, max_search) {
+                Some(plan) => break plan,
+                None => {
+                    new_slots = new_slots.checked_mul(2).ok_or(DiskError::IndexRegionFull)?;
+                }
+            }
+        };
+
+        self.index_file
+            .set_len((new_slots * self.slot_size) as u64)
+            .map_err(|_| DiskError::IndexRegionFull)?;
+        self.index =
+            unsafe { MmapMut::map_mut(&self.index_file).map_err(|_| DiskError::IndexRegionFull)? };
+        self.index.fill(EMPTY);
+        self.slots = new_slots;
+
+        for (slot, key, data_index) in placements {
+            self.write_slot(slot, key, data_index);
+        }
+        Ok(())
+    }
+
+    /// Computes a bounded-probe placement of `old` into a table of `slots`,
+    /// or `None` if any entry cannot be seated within `max_search` of home.
+    fn plan_placement(
+        old: &[(K, u64)],
+        slots: usize,
+        max_search: usize,
+    ) -> Option<Vec<(usize, K, u64)>> {
+        let mut occupied = vec![false; slots];
+        let mut plan = Vec::with_capacity(old.len());
+        for &(key, data_index) in old {
+            let home = (hash_key(&key) as usize) % slots;
+            let mut placed = false;
+            for i in 0..max_search {
+                let slot = (home + i) % slots;
+                if !occupied[slot] {
+                    occupied[slot] = true;
+                    plan.push((slot, key, data_index));
+                    placed = true;
+                    break;
+                }
+            }
+            if !placed {
+                return None;
+            }
+        }
+        Some(plan)
+    }
+
+    /// Doubles the data region.
+    fn grow_data(&mut self) -> Result<(), DiskError> {
+        let new_cap = self.data_cap.checked_mul(2).ok_or(DiskError::DataRegionFull)?;
+        self.data.flush().map_err(|_| DiskError::DataRegionFull)?;
+        self.data_file
+            .set_len((new_cap * mem::size_of::<V>()) as u64)
+            .map_err(|_| DiskError::DataRegionFull)?;
+        self.data =
+            unsafe { MmapMut::map_mut(&self.data_file).map_err(|_| DiskError::DataRegionFull)? };
+        self.data_cap = new_cap;
+        Ok(())
+    }
+}
+
+enum Probe {
+    Occupied(usize),
+    Vacant(usize),
+}
+
+/// Hashes a key with a fixed hasher so placement is stable across the lifetime
+/// of the mapping (unlike the randomized in-memory [`RandomState`](crate::RandomState)).
+fn hash_key<K: Hash>(key: &K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A hash map whose buckets live in memory-mapped files. See the [module
+/// documentation](self) for the storage model.
+pub struct DiskMap<K, V> {
+    buckets: Vec<Bucket<K, V>>,
+    shift: u32,
+    max_search: usize,
+}
+
+impl<K, V> DiskMap<K, V>
+where
+    K: Copy + Hash + Eq,
+    V: Copy,
+{
+    pub fn new(config: DiskConfig) -> Result<Self, DiskError> {
+        let num_buckets = config.num_buckets.max(1).next_power_of_two();
+        std::fs::create_dir_all(&config.dir)?;
+        // Index regions start wide enough to hold a full probe window.
+        let initial_slots = config.max_search.max(1).next_power_of_two();
+        let mut buckets = Vec::with_capacity(num_buckets);
+        for bucket in 0..num_buckets {
+            buckets.push(Bucket::open(&config.dir, bucket, initial_slots)?);
+        }
+        Ok(DiskMap {
+            buckets,
+            shift: 64 - num_buckets.trailing_zeros(),
+            max_search: config.max_search,
+        })
+    }
+
+    /// Selects the bucket using the high bits of the hash. With a single bucket
+    /// `shift` is 64, which would overflow a `u64` shift, so `checked_shr`
+    /// folds that case to bucket 0.
+    fn bucket_of(&self, hash: u64) -> usize {
+        hash.checked_shr(self.shift).unwrap_or(0) as usize
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Result<(), DiskError> {
+        let hash = hash_key(&key);
+        let max_search = self.max_search;
+        let bucket = self.bucket_of(hash);
+        self.buckets[bucket].insert(hash, key, value, max_search)
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let hash = hash_key(key);
+        let bucket = self.bucket_of(hash);
+        self.buckets[bucket].get(hash, key, self.max_search)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let hash = hash_key(key);
+        let max_search = self.max_search;
+        let bucket = self.bucket_of(hash);
+        self.buckets[bucket].remove(hash, key, max_search)
+    }
+}
@@ -1,65 +1,199 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use std::cell::Cell;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::marker::PhantomData;
 use std::mem;
 
-const INITIAL_NUM_BUCKETS: usize = 1;
+mod disk;
 
-pub struct HashMap<K, V> {
-    buckets: Vec<Vec<(K, V)>>,
+pub use disk::{DiskConfig, DiskError, DiskMap};
+
+/// Smallest table we ever allocate: a single probe group.
+const INITIAL_CAPACITY: usize = 16;
+/// Number of control bytes scanned per probe step.
+const GROUP_WIDTH: usize = 16;
+
+/// Control byte for a never-used slot. Scanning stops when one is seen.
+const EMPTY: u8 = 0xFF;
+/// Control byte for a slot whose entry was removed; counts toward load but does
+/// not terminate a probe.
+const DELETED: u8 = 0x80;
+
+/// Top 7 bits of the hash, stored in a full slot's control byte. The high bit
+/// is always clear, which is what distinguishes it from [`EMPTY`]/[`DELETED`].
+fn h2(hash: u64) -> u8 {
+    (hash >> 57) as u8
+}
+
+/// Result of probing for a key: either the slot holding it, or the first slot
+/// an insertion may claim.
+enum Locate {
+    Occupied(usize),
+    Vacant(usize),
+}
+
+pub struct HashMap<K, V, S = RandomState> {
+    ctrl: Vec<u8>,
+    slots: Vec<Option<(K, V)>>,
     items: usize,
+    tombstones: usize,
+    hash_builder: S,
 }
 
-impl<K, V> HashMap<K, V> {
+impl<K, V> HashMap<K, V, RandomState> {
     pub fn new() -> Self {
+        HashMap::with_hasher(RandomState::new())
+    }
+
+    /// Builds a disk-backed map instead of an in-heap table, partitioning keys
+    /// across memory-mapped bucket files. See [`DiskMap`] for the storage model
+    /// and the `Copy`/fixed-size constraints it imposes.
+    pub fn with_disk_backing(config: DiskConfig) -> Result<DiskMap<K, V>, DiskError>
+    where
+        K: Copy + Hash + Eq,
+        V: Copy,
+    {
+        DiskMap::new(config)
+    }
+}
+
+impl<K, V, S> HashMap<K, V, S> {
+    pub fn with_hasher(hash_builder: S) -> Self {
         HashMap {
-            buckets: Vec::new(),
+            ctrl: Vec::new(),
+            slots: Vec::new(),
             items: 0,
+            tombstones: 0,
+            hash_builder,
         }
     }
+
+    fn capacity(&self) -> usize {
+        self.ctrl.len()
+    }
 }
 
-impl<K, V> HashMap<K, V>
+impl<K, V, S> HashMap<K, V, S>
 where
     K: Hash + Eq,
+    S: BuildHasher,
 {
+    fn hash(&self, key: &K) -> u64 {
+        self.hash_builder.hash_one(key)
+    }
+
+    /// Whether inserting one more live entry would push the table (counting
+    /// tombstones) past the 7/8 load factor.
+    fn needs_resize(&self) -> bool {
+        self.capacity() == 0 || (self.items + self.tombstones + 1) * 8 > self.capacity() * 7
+    }
+
     fn resize(&mut self) {
-        let target_size = match self.buckets.len() {
-            0 => INITIAL_NUM_BUCKETS,
-            n => n * 2,
+        let cap = self.capacity();
+        let new_cap = if cap == 0 {
+            INITIAL_CAPACITY
+        } else if self.items * 2 >= cap {
+            cap * 2
+        } else {
+            // Enough dead weight is tombstones; rehash in place to reclaim it.
+            cap
         };
 
-        let mut new_buckets = Vec::with_capacity(target_size);
-        new_buckets.extend((0..target_size).map(|_| vec![]));
+        let old_slots = mem::replace(
+            &mut self.slots,
+            (0..new_cap).map(|_| None).collect::<Vec<_>>(),
+        );
+        self.ctrl = vec![EMPTY; new_cap];
+        self.items = 0;
+        self.tombstones = 0;
 
-        for (key, value) in self.buckets.iter_mut().flat_map(|bucket| bucket.drain(..)) {
-            let index = Self::hash_index(&key, new_buckets.len());
-            new_buckets[index].push((key, value));
+        for (key, value) in old_slots.into_iter().flatten() {
+            let hash = self.hash(&key);
+            if let Locate::Vacant(slot) = self.locate(hash, &key) {
+                self.ctrl[slot] = h2(hash);
+                self.slots[slot] = Some((key, value));
+                self.items += 1;
+            }
         }
+    }
 
-        self.buckets = new_buckets;
+    /// Triangular probe for `key`, starting from the group its hash lands in.
+    /// Assumes the table is non-empty and below full load so a vacant slot is
+    /// always reachable.
+    fn locate(&self, hash: u64, key: &K) -> Locate {
+        let mask = self.capacity() - 1;
+        let tag = h2(hash);
+        let mut pos = (hash as usize) & mask;
+        let mut stride = 0;
+        let mut insert = None;
+        loop {
+            for i in 0..GROUP_WIDTH {
+                let idx = (pos + i) & mask;
+                let ctrl = self.ctrl[idx];
+                if ctrl == tag {
+                    if let Some((k, _)) = &self.slots[idx] {
+                        if k == key {
+                            return Locate::Occupied(idx);
+                        }
+                    }
+                } else if ctrl == EMPTY {
+                    return Locate::Vacant(insert.unwrap_or(idx));
+                } else if ctrl == DELETED && insert.is_none() {
+                    insert = Some(idx);
+                }
+            }
+            stride += GROUP_WIDTH;
+            pos = (pos + stride) & mask;
+        }
     }
 
     pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-        self.items += 1;
-        if self.items > self.buckets.len() * 3 / 4 {
+        if self.needs_resize() {
             self.resize();
         }
-        let index = Self::hash_index(&key, self.buckets.len());
-        let bucket = &mut self.buckets[index];
-        for &mut (ref k, ref mut v) in bucket.iter_mut() {
-            if k == &key {
-                return Some(mem::replace(v, value));
+        let hash = self.hash(&key);
+        match self.locate(hash, &key) {
+            Locate::Occupied(idx) => {
+                let (_, v) = self.slots[idx].as_mut().unwrap();
+                Some(mem::replace(v, value))
+            }
+            Locate::Vacant(idx) => {
+                if self.ctrl[idx] == DELETED {
+                    self.tombstones -= 1;
+                }
+                self.ctrl[idx] = h2(hash);
+                self.slots[idx] = Some((key, value));
+                self.items += 1;
+                None
             }
         }
-        bucket.push((key, value));
-        None
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        if self.needs_resize() {
+            self.resize();
+        }
+        let hash = self.hash(&key);
+        match self.locate(hash, &key) {
+            Locate::Occupied(idx) => Entry::Occupied(OccupiedEntry {
+                entry: self.slots[idx].as_mut().unwrap(),
+            }),
+            Locate::Vacant(slot) => Entry::Vacant(VacantEntry {
+                key,
+                map: self,
+                hash,
+                slot,
+            }),
+        }
     }
 
     pub fn get(&self, key: &K) -> Option<&V> {
-        self.buckets[Self::hash_index(key, self.buckets.len())]
-            .iter()
-            .find(|(k, _)| k == key)
-            .map(|(_, v)| v)
+        if self.capacity() == 0 {
+            return None;
+        }
+        match self.locate(self.hash(key), key) {
+            Locate::Occupied(idx) => self.slots[idx].as_ref().map(|(_, v)| v),
+            Locate::Vacant(_) => None,
+        }
     }
 
     pub fn contains_key(&self, key: &K) -> bool {
@@ -67,12 +201,19 @@ where
     }
 
     pub fn remove(&mut self, key: &K) -> Option<V> {
-        let index = Self::hash_index(key, self.buckets.len());
-        let bucket = &mut self.buckets[index];
-        let i = bucket.iter().position(|(k, _)| k == key)?;
-        let (_, v) = bucket.swap_remove(i);
-        self.items -= 1;
-        Some(v)
+        if self.capacity() == 0 {
+            return None;
+        }
+        let hash = self.hash(key);
+        match self.locate(hash, key) {
+            Locate::Occupied(idx) => {
+                self.ctrl[idx] = DELETED;
+                self.tombstones += 1;
+                self.items -= 1;
+                self.slots[idx].take().map(|(_, v)| v)
+            }
+            Locate::Vacant(_) => None,
+        }
     }
 
     pub fn len(&self) -> usize {
@@ -83,59 +224,521 @@ where
         self.items == 0
     }
 
-    fn hash_index(key: &K, table_size: usize) -> usize {
-        let mut hasher = DefaultHasher::new();
-        key.hash(&mut hasher);
-        (hasher.finish() % table_size as u64) as usize
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            inner: self.slots.iter_mut(),
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V, S> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V, S> {
+        Values { inner: self.iter() }
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    /// Clears the map, returning all entries as an owning iterator. The table
+    /// keeps its allocated capacity for reuse.
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        let cap = self.ctrl.len();
+        let slots = mem::replace(&mut self.slots, (0..cap).map(|_| None).collect());
+        for ctrl in &mut self.ctrl {
+            *ctrl = EMPTY;
+        }
+        self.items = 0;
+        self.tombstones = 0;
+        Drain {
+            inner: slots.into_iter(),
+            marker: PhantomData,
+        }
+    }
+
+    fn iter(&self) -> Iter<'_, K, V, S> {
+        Iter::new(self)
+    }
+}
+
+impl<K, V> Default for HashMap<K, V, RandomState> {
+    fn default() -> Self {
+        HashMap::new()
+    }
+}
+
+/// A [`BuildHasher`] that seeds every map instance with its own pair of keys so
+/// two maps in the same process hash along different sequences, frustrating
+/// collision attacks crafted against a single fixed hash.
+pub struct RandomState {
+    k0: u64,
+    k1: u64,
+}
+
+impl RandomState {
+    pub fn new() -> Self {
+        thread_local! {
+            static COUNTER: Cell<u64> = const { Cell::new(0) };
+        }
+        let counter = COUNTER.with(|c| {
+            let n = c.get().wrapping_add(1);
+            c.set(n);
+            n
+        });
+        // Mix the per-thread counter with a live stack address so the keys vary
+        // both within and across threads without needing an RNG dependency.
+        let addr = &counter as *const u64 as u64;
+        let k0 = counter
+            .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            .wrapping_add(addr);
+        let k1 = addr
+            .rotate_left(32)
+            .wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+            .wrapping_add(counter);
+        RandomState { k0, k1 }
+    }
+}
+
+impl Default for RandomState {
+    fn default() -> Self {
+        RandomState::new()
+    }
+}
+
+impl BuildHasher for RandomState {
+    type Hasher = SeededHasher;
+    fn build_hasher(&self) -> SeededHasher {
+        SeededHasher {
+            state: self.k0,
+            key: self.k1,
+        }
     }
 }
 
-pub struct Iter<'a, K, V> {
-    map: &'a HashMap<K, V>,
-    bucket_index: usize,
-    elem_index: usize,
+/// The keyed [`Hasher`] produced by [`RandomState`].
+pub struct SeededHasher {
+    state: u64,
+    key: u64,
 }
 
-impl<'a, K, V> Iterator for Iter<'a, K, V> {
+impl Hasher for SeededHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state =
+                (self.state.rotate_left(5) ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut h = self.state ^ self.key;
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+        h ^= h >> 33;
+        h
+    }
+}
+
+pub enum Entry<'a, K, V, S = RandomState> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    entry: &'a mut (K, V),
+}
+
+pub struct VacantEntry<'a, K, V, S = RandomState> {
+    key: K,
+    map: &'a mut HashMap<K, V, S>,
+    hash: u64,
+    slot: usize,
+}
+
+impl<'a, K, V, S> VacantEntry<'a, K, V, S> {
+    fn insert(self, value: V) -> &'a mut V {
+        if self.map.ctrl[self.slot] == DELETED {
+            self.map.tombstones -= 1;
+        }
+        self.map.ctrl[self.slot] = h2(self.hash);
+        self.map.slots[self.slot] = Some((self.key, value));
+        self.map.items += 1;
+        &mut self.map.slots[self.slot].as_mut().unwrap().1
+    }
+}
+
+impl<'a, K, V, S> Entry<'a, K, V, S> {
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => &mut e.entry.1,
+            Entry::Vacant(e) => e.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F>(self, f: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Entry::Occupied(e) => &mut e.entry.1,
+            Entry::Vacant(e) => e.insert(f()),
+        }
+    }
+
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        match self {
+            Entry::Occupied(e) => {
+                f(&mut e.entry.1);
+                Entry::Occupied(e)
+            }
+            Entry::Vacant(e) => Entry::Vacant(e),
+        }
+    }
+
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(e) => &e.entry.0,
+            Entry::Vacant(e) => &e.key,
+        }
+    }
+}
+
+pub struct Iter<'a, K, V, S = RandomState> {
+    map: &'a HashMap<K, V, S>,
+    slot: usize,
+}
+
+impl<'a, K, V, S> Iterator for Iter<'a, K, V, S> {
     type Item = (&'a K, &'a V);
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            match self.map.buckets.get(self.bucket_index) {
-                Some(bucket) => match bucket.get(self.elem_index) {
-                    Some((k, v)) => {
-                        self.elem_index += 1;
-                        break Some((&k, &v));
-                    }
-                    None => {
-                        self.bucket_index += 1;
-                        self.elem_index = 0;
-                        continue;
+            match self.map.slots.get(self.slot) {
+                Some(slot) => {
+                    self.slot += 1;
+                    if let Some((k, v)) = slot {
+                        break Some((k, v));
                     }
-                },
+                }
                 None => break None,
             }
         }
     }
 }
 
-impl<'a, K, V> Iter<'a, K, V> {
-    fn new(map: &'a HashMap<K, V>) -> Self {
-        Iter {
-            map,
-            bucket_index: 0,
-            elem_index: 0,
-        }
+impl<'a, K, V, S> Iter<'a, K, V, S> {
+    fn new(map: &'a HashMap<K, V, S>) -> Self {
+        Iter { map, slot: 0 }
     }
 }
 
-impl<'a, K, V> IntoIterator for &'a HashMap<K, V> {
+impl<'a, K, V, S> IntoIterator for &'a HashMap<K, V, S> {
     type Item = (&'a K, &'a V);
-    type IntoIter = Iter<'a, K, V>;
-    fn into_iter(self) -> Iter<'a, K, V> {
+    type IntoIter = Iter<'a, K, V, S>;
+    fn into_iter(self) -> Iter<'a, K, V, S> {
         Iter::new(self)
     }
 }
 
+pub struct IterMut<'a, K, V> {
+    inner: std::slice::IterMut<'a, Option<(K, V)>>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .by_ref()
+            .flatten()
+            .next()
+            .map(|(k, v)| (&*k, v))
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+pub struct IntoIter<K, V> {
+    inner: std::vec::IntoIter<Option<(K, V)>>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.by_ref().flatten().next()
+    }
+}
+
+impl<K, V, S> IntoIterator for HashMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            inner: self.slots.into_iter(),
+        }
+    }
+}
+
+pub struct Drain<'a, K, V> {
+    inner: std::vec::IntoIter<Option<(K, V)>>,
+    marker: PhantomData<&'a mut (K, V)>,
+}
+
+impl<K, V> Iterator for Drain<'_, K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.by_ref().flatten().next()
+    }
+}
+
+pub struct Keys<'a, K, V, S = RandomState> {
+    inner: Iter<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Iterator for Keys<'a, K, V, S> {
+    type Item = &'a K;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, _)| k)
+    }
+}
+
+pub struct Values<'a, K, V, S = RandomState> {
+    inner: Iter<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Iterator for Values<'a, K, V, S> {
+    type Item = &'a V;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, v)| v)
+    }
+}
+
+pub struct ValuesMut<'a, K, V> {
+    inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, v)| v)
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for HashMap<K, V, RandomState>
+where
+    K: Hash + Eq,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = HashMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V, S> Extend<(K, V)> for HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+/// A hash set implemented as a [`HashMap`] where the values are `()`.
+///
+/// All hashing and resizing is delegated to the underlying map, so the set
+/// shares one table implementation with it.
+pub struct HashSet<T, S = RandomState> {
+    map: HashMap<T, (), S>,
+}
+
+impl<T> HashSet<T, RandomState> {
+    pub fn new() -> Self {
+        HashSet {
+            map: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Default for HashSet<T, RandomState> {
+    fn default() -> Self {
+        HashSet::new()
+    }
+}
+
+impl<T, S> HashSet<T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Adds `value`, returning `true` if it was not already present.
+    pub fn insert(&mut self, value: T) -> bool {
+        self.map.insert(value, ()).is_none()
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.map.contains_key(value)
+    }
+
+    /// Removes `value`, returning `true` if it was present.
+    pub fn remove(&mut self, value: &T) -> bool {
+        self.map.remove(value).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn iter(&self) -> SetIter<'_, T, S> {
+        SetIter {
+            inner: self.map.iter(),
+        }
+    }
+
+    /// Visits the values present in both sets.
+    pub fn intersection<'a>(&'a self, other: &'a HashSet<T, S>) -> Intersection<'a, T, S> {
+        Intersection {
+            iter: self.iter(),
+            other,
+        }
+    }
+
+    /// Visits the values in `self` that are not in `other`.
+    pub fn difference<'a>(&'a self, other: &'a HashSet<T, S>) -> Difference<'a, T, S> {
+        Difference {
+            iter: self.iter(),
+            other,
+        }
+    }
+
+    /// Visits the values in either set, without duplicates.
+    pub fn union<'a>(&'a self, other: &'a HashSet<T, S>) -> Union<'a, T, S> {
+        Union {
+            iter: self.iter(),
+            rest: other.difference(self),
+        }
+    }
+
+    /// Visits the values in exactly one of the two sets.
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a HashSet<T, S>,
+    ) -> SymmetricDifference<'a, T, S> {
+        SymmetricDifference {
+            iter: self.difference(other),
+            rest: other.difference(self),
+        }
+    }
+}
+
+pub struct SetIter<'a, T, S = RandomState> {
+    inner: Iter<'a, T, (), S>,
+}
+
+impl<'a, T, S> Iterator for SetIter<'a, T, S> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(t, _)| t)
+    }
+}
+
+impl<'a, T, S> IntoIterator for &'a HashSet<T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = &'a T;
+    type IntoIter = SetIter<'a, T, S>;
+    fn into_iter(self) -> SetIter<'a, T, S> {
+        self.iter()
+    }
+}
+
+pub struct Intersection<'a, T, S = RandomState> {
+    iter: SetIter<'a, T, S>,
+    other: &'a HashSet<T, S>,
+}
+
+impl<'a, T, S> Iterator for Intersection<'a, T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.by_ref().find(|&t| self.other.contains(t))
+    }
+}
+
+pub struct Difference<'a, T, S = RandomState> {
+    iter: SetIter<'a, T, S>,
+    other: &'a HashSet<T, S>,
+}
+
+impl<'a, T, S> Iterator for Difference<'a, T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.by_ref().find(|&t| !self.other.contains(t))
+    }
+}
+
+pub struct Union<'a, T, S = RandomState> {
+    iter: SetIter<'a, T, S>,
+    rest: Difference<'a, T, S>,
+}
+
+impl<'a, T, S> Iterator for Union<'a, T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().or_else(|| self.rest.next())
+    }
+}
+
+pub struct SymmetricDifference<'a, T, S = RandomState> {
+    iter: Difference<'a, T, S>,
+    rest: Difference<'a, T, S>,
+}
+
+impl<'a, T, S> Iterator for SymmetricDifference<'a, T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().or_else(|| self.rest.next())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,4 +777,174 @@ mod tests {
         }
         assert_eq!((&map).into_iter().count(), 4);
     }
+
+    #[test]
+    fn entry() {
+        let mut map = HashMap::new();
+        *map.entry("foo").or_insert(0) += 1;
+        *map.entry("foo").or_insert(0) += 1;
+        map.entry("bar").or_insert_with(|| 10);
+        map.entry("bar").and_modify(|v| *v += 5).or_insert(0);
+        assert_eq!(map.get(&"foo"), Some(&2));
+        assert_eq!(map.get(&"bar"), Some(&15));
+        assert_eq!(map.entry("baz").key(), &"baz");
+    }
+
+    #[test]
+    fn distinct_seeds_per_instance() {
+        let a = RandomState::new();
+        let b = RandomState::new();
+        assert_ne!(a.hash_one("collision"), b.hash_one("collision"));
+    }
+
+    #[test]
+    fn grows_and_survives_tombstones() {
+        let mut map = HashMap::new();
+        for i in 0..1_000 {
+            assert_eq!(map.insert(i, i * 2), None);
+        }
+        assert_eq!(map.len(), 1_000);
+        // Churn removals and re-inserts to exercise tombstone reclamation.
+        for i in 0..1_000 {
+            if i % 3 == 0 {
+                assert_eq!(map.remove(&i), Some(i * 2));
+            }
+        }
+        for i in 0..1_000 {
+            if i % 3 == 0 {
+                assert_eq!(map.get(&i), None);
+                map.insert(i, i * 2);
+            }
+        }
+        for i in 0..1_000 {
+            assert_eq!(map.get(&i), Some(&(i * 2)));
+        }
+        assert_eq!(map.len(), 1_000);
+    }
+
+    #[test]
+    fn set_operations() {
+        let mut a = HashSet::new();
+        let mut b = HashSet::new();
+        for v in [1, 2, 3] {
+            assert!(a.insert(v));
+        }
+        assert!(!a.insert(1));
+        for v in [3, 4, 5] {
+            b.insert(v);
+        }
+        assert_eq!(a.len(), 3);
+        assert!(a.contains(&2));
+        assert!(a.remove(&2));
+        assert!(!a.contains(&2));
+        a.insert(2);
+
+        let mut inter: Vec<_> = a.intersection(&b).copied().collect();
+        inter.sort_unstable();
+        assert_eq!(inter, vec![3]);
+
+        let mut diff: Vec<_> = a.difference(&b).copied().collect();
+        diff.sort_unstable();
+        assert_eq!(diff, vec![1, 2]);
+
+        let mut union: Vec<_> = a.union(&b).copied().collect();
+        union.sort_unstable();
+        assert_eq!(union, vec![1, 2, 3, 4, 5]);
+
+        let mut sym: Vec<_> = a.symmetric_difference(&b).copied().collect();
+        sym.sort_unstable();
+        assert_eq!(sym, vec![1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn iteration_family() {
+        let mut map: HashMap<&str, i32> = [("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+        map.extend([("d", 4)]);
+
+        for (_, v) in map.iter_mut() {
+            *v *= 10;
+        }
+        for v in map.values_mut() {
+            *v += 1;
+        }
+
+        let mut keys: Vec<_> = map.keys().copied().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, vec!["a", "b", "c", "d"]);
+
+        let mut values: Vec<_> = map.values().copied().collect();
+        values.sort_unstable();
+        assert_eq!(values, vec![11, 21, 31, 41]);
+
+        let mut owned: Vec<_> = map.into_iter().collect();
+        owned.sort_unstable();
+        assert_eq!(owned, vec![("a", 11), ("b", 21), ("c", 31), ("d", 41)]);
+    }
+
+    #[test]
+    fn drain_empties_but_keeps_usable() {
+        let mut map: HashMap<i32, i32> = (0..10).map(|i| (i, i)).collect();
+        let mut drained: Vec<_> = map.drain().collect();
+        drained.sort_unstable();
+        assert_eq!(drained, (0..10).map(|i| (i, i)).collect::<Vec<_>>());
+        assert!(map.is_empty());
+        map.insert(99, 99);
+        assert_eq!(map.get(&99), Some(&99));
+    }
+
+    fn disk_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("hashmap-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn disk_single_bucket_is_usable() {
+        // `num_buckets: 1` yields a 64-bit shift in bucket selection; it must
+        // not overflow or index out of bounds.
+        let config = DiskConfig {
+            num_buckets: 1,
+            dir: disk_dir("single"),
+            max_search: 8,
+        };
+        let mut map = HashMap::<u64, u64>::with_disk_backing(config).unwrap();
+        for i in 0..32 {
+            map.insert(i, i * 2).unwrap();
+        }
+        for i in 0..32 {
+            assert_eq!(map.get(&i), Some(i * 2));
+        }
+    }
+
+    #[test]
+    fn disk_removal_does_not_shadow_probe_chain() {
+        let config = DiskConfig {
+            num_buckets: 4,
+            dir: disk_dir("tombstone"),
+            max_search: 16,
+        };
+        let mut map = HashMap::<u64, u64>::with_disk_backing(config).unwrap();
+        for i in 0..200 {
+            map.insert(i, i).unwrap();
+        }
+        // Remove every other key, then confirm the survivors remain findable
+        // even when they sit past a freed slot in their probe chain.
+        for i in (0..200).step_by(2) {
+            assert_eq!(map.remove(&i), Some(i));
+        }
+        for i in 0..200 {
+            if i % 2 == 0 {
+                assert_eq!(map.get(&i), None);
+            } else {
+                assert_eq!(map.get(&i), Some(i));
+            }
+        }
+        // Re-inserting reuses freed data slots rather than growing unboundedly.
+        for i in (0..200).step_by(2) {
+            map.insert(i, i + 1000).unwrap();
+        }
+        for i in (0..200).step_by(2) {
+            assert_eq!(map.get(&i), Some(i + 1000));
+        }
+    }
 }